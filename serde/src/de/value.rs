@@ -37,6 +37,9 @@
 
 use lib::*;
 
+#[cfg(feature = "indexmap")]
+extern crate indexmap;
+
 use de::{self, IntoDeserializer, Expected, SeqAccess};
 use private::de::size_hint;
 use self::private::{First, Second};
@@ -339,6 +342,77 @@ where
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// A helper deserializer that deserializes a `&str` with a lifetime tying it to
+/// the input, so that `Deserialize` impls that borrow out of the input can do
+/// so via `visit_borrowed_str`.
+#[derive(Clone, Debug)]
+pub struct BorrowedStrDeserializer<'de, E> {
+    value: &'de str,
+    marker: PhantomData<E>,
+}
+
+impl<'de, E> BorrowedStrDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    /// Create a new borrowed deserializer from the given borrowed string.
+    pub fn new(value: &'de str) -> BorrowedStrDeserializer<'de, E> {
+        BorrowedStrDeserializer {
+            value: value,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, E> de::Deserializer<'de> for BorrowedStrDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
+        seq seq_fixed_size bytes map unit_struct newtype_struct tuple_struct
+        struct identifier tuple ignored_any byte_buf
+    }
+}
+
+impl<'de, E> de::EnumAccess<'de> for BorrowedStrDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+    type Variant = private::UnitOnly<E>;
+
+    fn variant_seed<T>(self, seed: T) -> Result<(T::Value, Self::Variant), Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self).map(private::unit_only)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 /// A helper deserializer that deserializes a `String`.
 #[cfg(any(feature = "std", feature = "collections"))]
 #[derive(Clone, Debug)]
@@ -490,6 +564,160 @@ where
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// A helper deserializer that deserializes a `&[u8]`.
+#[derive(Clone, Debug)]
+pub struct BytesDeserializer<'a, E> {
+    value: &'a [u8],
+    marker: PhantomData<E>,
+}
+
+impl<'a, E> BytesDeserializer<'a, E>
+where
+    E: de::Error,
+{
+    /// Create a new deserializer from the given bytes.
+    pub fn new(value: &'a [u8]) -> Self {
+        BytesDeserializer {
+            value: value,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'a, E> IntoDeserializer<'de, E> for &'a [u8]
+where
+    E: de::Error,
+{
+    type Deserializer = BytesDeserializer<'a, E>;
+
+    fn into_deserializer(self) -> BytesDeserializer<'a, E> {
+        BytesDeserializer::new(self)
+    }
+}
+
+impl<'de, 'a, E> de::Deserializer<'de> for BytesDeserializer<'a, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_bytes(self.value)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
+        seq seq_fixed_size bytes map unit_struct newtype_struct tuple_struct
+        struct identifier tuple enum ignored_any byte_buf
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A helper deserializer that deserializes a `&[u8]` with a lifetime tying it
+/// to the input, so that `Deserialize` impls can borrow the bytes via
+/// `visit_borrowed_bytes`.
+///
+/// There is no `IntoDeserializer` for `&'de [u8]`: that impl already exists for
+/// `&[u8]` (producing a [`BytesDeserializer`]), and a borrowed slice cannot
+/// distinguish itself from an ordinary one at the trait level. Construct this
+/// deserializer explicitly with [`new`](BorrowedBytesDeserializer::new) when a
+/// borrowed lifetime must be preserved.
+#[derive(Clone, Debug)]
+pub struct BorrowedBytesDeserializer<'de, E> {
+    value: &'de [u8],
+    marker: PhantomData<E>,
+}
+
+impl<'de, E> BorrowedBytesDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    /// Create a new borrowed deserializer from the given borrowed bytes.
+    pub fn new(value: &'de [u8]) -> Self {
+        BorrowedBytesDeserializer {
+            value: value,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, E> de::Deserializer<'de> for BorrowedBytesDeserializer<'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.value)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
+        seq seq_fixed_size bytes map unit_struct newtype_struct tuple_struct
+        struct identifier tuple enum ignored_any byte_buf
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A helper deserializer that deserializes a `Vec<u8>`.
+///
+/// There is no `IntoDeserializer` for `Vec<u8>`: it would collide with the
+/// blanket `impl IntoDeserializer for Vec<T>`, which deserializes a `Vec` as a
+/// sequence of its elements. Construct this deserializer explicitly with
+/// [`new`](ByteBufDeserializer::new) to treat the bytes as a byte buffer
+/// instead.
+#[cfg(any(feature = "std", feature = "collections"))]
+#[derive(Clone, Debug)]
+pub struct ByteBufDeserializer<E> {
+    value: Vec<u8>,
+    marker: PhantomData<E>,
+}
+
+#[cfg(any(feature = "std", feature = "collections"))]
+impl<E> ByteBufDeserializer<E>
+where
+    E: de::Error,
+{
+    /// Create a new deserializer from the given byte buffer.
+    pub fn new(value: Vec<u8>) -> Self {
+        ByteBufDeserializer {
+            value: value,
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "collections"))]
+impl<'de, E> de::Deserializer<'de> for ByteBufDeserializer<E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_byte_buf(self.value)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
+        seq seq_fixed_size bytes map unit_struct newtype_struct tuple_struct
+        struct identifier tuple enum ignored_any byte_buf
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 /// A helper deserializer that deserializes a sequence.
 #[derive(Clone, Debug)]
 pub struct SeqDeserializer<I, E> {
@@ -514,7 +742,14 @@ where
 
     /// Check for remaining elements after passing a `SeqDeserializer` to
     /// `Visitor::visit_seq`.
-    pub fn end(mut self) -> Result<(), E> {
+    pub fn end(self) -> Result<(), E> {
+        self.try_end().map_err(|(_, err)| err)
+    }
+
+    /// Like `end`, but on failure returns the number of trailing elements that
+    /// were left over alongside the error, letting callers choose to ignore the
+    /// surplus rather than always erroring.
+    pub fn try_end(mut self) -> Result<(), (usize, E)> {
         let mut remaining = 0;
         while self.iter.next().is_some() {
             remaining += 1;
@@ -524,7 +759,8 @@ where
         } else {
             // First argument is the number of elements in the data, second
             // argument is the number of elements expected by the Deserialize.
-            Err(de::Error::invalid_length(self.count + remaining, &ExpectedInSeq(self.count)),)
+            let err = de::Error::invalid_length(self.count + remaining, &ExpectedInSeq(self.count));
+            Err((remaining, err))
         }
     }
 }
@@ -669,6 +905,53 @@ where
 
 ////////////////////////////////////////////////////////////////////////////////
 
+/// A deserializer holding an `EnumAccess`.
+#[derive(Clone, Debug)]
+pub struct EnumAccessDeserializer<A> {
+    access: A,
+}
+
+impl<A> EnumAccessDeserializer<A> {
+    /// Construct a new `EnumAccessDeserializer<A>`.
+    pub fn new(access: A) -> Self {
+        EnumAccessDeserializer { access: access }
+    }
+}
+
+impl<'de, A> de::Deserializer<'de> for EnumAccessDeserializer<A>
+where
+    A: de::EnumAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_enum(self.access)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_enum(self.access)
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
+        seq seq_fixed_size bytes map unit_struct newtype_struct tuple_struct
+        struct identifier tuple ignored_any byte_buf
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 /// A helper deserializer that deserializes a map.
 pub struct MapDeserializer<'de, I, E>
 where
@@ -706,7 +989,14 @@ where
 
     /// Check for remaining elements after passing a `MapDeserializer` to
     /// `Visitor::visit_map`.
-    pub fn end(mut self) -> Result<(), E> {
+    pub fn end(self) -> Result<(), E> {
+        self.try_end().map_err(|(_, err)| err)
+    }
+
+    /// Like `end`, but on failure returns the number of trailing elements that
+    /// were left over alongside the error, letting callers choose to ignore the
+    /// surplus rather than always erroring.
+    pub fn try_end(mut self) -> Result<(), (usize, E)> {
         let mut remaining = 0;
         while self.iter.next().is_some() {
             remaining += 1;
@@ -716,7 +1006,8 @@ where
         } else {
             // First argument is the number of elements in the data, second
             // argument is the number of elements expected by the Deserialize.
-            Err(de::Error::invalid_length(self.count + remaining, &ExpectedInMap(self.count)),)
+            let err = de::Error::invalid_length(self.count + remaining, &ExpectedInMap(self.count));
+            Err((remaining, err))
         }
     }
 
@@ -731,6 +1022,22 @@ where
     }
 }
 
+// Report the kind of value actually present (a map, or a key/value pair seq)
+// when a heterogeneous value deserializer is asked for a type it cannot
+// satisfy, rather than falling back to a `invalid_length` mismatch.
+macro_rules! deserialize_unexpected_type {
+    ($unexpected:expr; $($method:ident)*) => {
+        $(
+            fn $method<__V>(self, visitor: __V) -> Result<__V::Value, Self::Error>
+            where
+                __V: de::Visitor<'de>,
+            {
+                Err(de::Error::invalid_type($unexpected, &visitor))
+            }
+        )*
+    }
+}
+
 impl<'de, I, E> de::Deserializer<'de> for MapDeserializer<'de, I, E>
 where
     I: Iterator,
@@ -750,6 +1057,15 @@ where
         Ok(value)
     }
 
+    deserialize_unexpected_type! {
+        de::Unexpected::Map;
+        deserialize_bool deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_unit deserialize_bytes
+        deserialize_byte_buf deserialize_identifier
+    }
+
     fn deserialize_seq<V_>(mut self, visitor: V_) -> Result<V_::Value, Self::Error>
     where
         V_: de::Visitor<'de>,
@@ -770,9 +1086,8 @@ where
     }
 
     forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
-        bytes map unit_struct newtype_struct tuple_struct struct identifier
-        tuple enum ignored_any byte_buf
+        option map unit_struct newtype_struct tuple_struct struct tuple enum
+        ignored_any
     }
 }
 
@@ -918,9 +1233,17 @@ where
     type Error = E;
 
     forward_to_deserialize_any! {
-        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
-        bytes map unit_struct newtype_struct tuple_struct struct identifier
-        tuple enum ignored_any byte_buf
+        option map unit_struct newtype_struct tuple_struct struct tuple enum
+        ignored_any
+    }
+
+    deserialize_unexpected_type! {
+        de::Unexpected::Seq;
+        deserialize_bool deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_unit deserialize_bytes
+        deserialize_byte_buf deserialize_identifier
     }
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -1036,6 +1359,20 @@ where
     }
 }
 
+#[cfg(feature = "indexmap")]
+impl<'de, K, V, E> IntoDeserializer<'de, E> for indexmap::IndexMap<K, V>
+where
+    K: IntoDeserializer<'de, E> + Eq + Hash,
+    V: IntoDeserializer<'de, E>,
+    E: de::Error,
+{
+    type Deserializer = MapDeserializer<'de, <indexmap::IndexMap<K, V> as IntoIterator>::IntoIter, E>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        MapDeserializer::new(self.into_iter())
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 
 /// A deserializer holding a `MapAccess`.
@@ -1064,10 +1401,104 @@ where
         visitor.visit_map(self.map)
     }
 
+    fn deserialize_enum<V>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
     forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit option
         seq seq_fixed_size bytes map unit_struct newtype_struct tuple_struct
-        struct identifier tuple enum ignored_any byte_buf
+        struct identifier tuple ignored_any byte_buf
+    }
+}
+
+impl<'de, A> de::EnumAccess<'de> for MapAccessDeserializer<A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = private::MapAsEnum<A>;
+
+    fn variant_seed<T>(mut self, seed: T) -> Result<(T::Value, Self::Variant), Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match try!(self.map.next_key_seed(seed)) {
+            Some(key) => Ok((key, private::map_as_enum(self.map))),
+            None => Err(de::Error::invalid_length(0, &ExpectedInMap(1))),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A `VariantAccess` that forwards every variant kind to an inner payload
+/// obtained through `IntoDeserializer`.
+///
+/// Unlike the `UnitOnly` helper, which can only ever produce C-like unit
+/// variants, this supports newtype, tuple, and struct variants as well, which
+/// is what value models carrying enum payloads require. It holds only the
+/// payload: it is the `Variant` half of an `EnumAccess`, and the variant
+/// identifier is produced by that `EnumAccess`'s `variant_seed`, not by this
+/// type.
+#[derive(Clone, Debug)]
+pub struct ValueVariantAccess<T, E> {
+    value: T,
+    marker: PhantomData<E>,
+}
+
+impl<T, E> ValueVariantAccess<T, E> {
+    /// Construct a new `ValueVariantAccess<T, E>` from a variant payload.
+    pub fn new(value: T) -> Self {
+        ValueVariantAccess {
+            value: value,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T, E> de::VariantAccess<'de> for ValueVariantAccess<T, E>
+where
+    T: IntoDeserializer<'de, E>,
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_unit(self) -> Result<(), Self::Error> {
+        de::Deserialize::deserialize(self.value.into_deserializer())
+    }
+
+    fn deserialize_newtype_seed<S>(self, seed: S) -> Result<S::Value, Self::Error>
+    where
+        S: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.value.into_deserializer())
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_tuple(self.value.into_deserializer(), len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.value.into_deserializer(), visitor)
     }
 }
 
@@ -1123,6 +1554,112 @@ mod private {
         }
     }
 
+    /// A `VariantAccess` that reads an externally tagged enum variant out of a
+    /// `MapAccess`, where the single map key is the variant and the single map
+    /// value is its content.
+    pub struct MapAsEnum<A> {
+        map: A,
+    }
+
+    pub fn map_as_enum<A>(map: A) -> MapAsEnum<A> {
+        MapAsEnum { map: map }
+    }
+
+    impl<A> MapAsEnum<A> {
+        fn end<'de>(mut self) -> Result<(), A::Error>
+        where
+            A: de::MapAccess<'de>,
+        {
+            match try!(self.map.next_key::<de::IgnoredAny>()) {
+                Some(_) => Err(de::Error::invalid_length(2, &super::ExpectedInMap(1))),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl<'de, A> de::VariantAccess<'de> for MapAsEnum<A>
+    where
+        A: de::MapAccess<'de>,
+    {
+        type Error = A::Error;
+
+        fn deserialize_unit(mut self) -> Result<(), Self::Error> {
+            try!(self.map.next_value::<()>());
+            self.end()
+        }
+
+        fn deserialize_newtype_seed<T>(mut self, seed: T) -> Result<T::Value, Self::Error>
+        where
+            T: de::DeserializeSeed<'de>,
+        {
+            let value = try!(self.map.next_value_seed(seed));
+            try!(self.end());
+            Ok(value)
+        }
+
+        fn deserialize_tuple<V>(mut self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            let value = try!(self.map.next_value_seed(SeedTupleVariant {
+                len: len,
+                visitor: visitor,
+            }));
+            try!(self.end());
+            Ok(value)
+        }
+
+        fn deserialize_struct<V>(
+            mut self,
+            _fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Self::Error>
+        where
+            V: de::Visitor<'de>,
+        {
+            let value = try!(self.map.next_value_seed(SeedStructVariant { visitor: visitor }));
+            try!(self.end());
+            Ok(value)
+        }
+    }
+
+    struct SeedTupleVariant<V> {
+        len: usize,
+        visitor: V,
+    }
+
+    impl<'de, V> de::DeserializeSeed<'de> for SeedTupleVariant<V>
+    where
+        V: de::Visitor<'de>,
+    {
+        type Value = V::Value;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            deserializer.deserialize_tuple(self.len, self.visitor)
+        }
+    }
+
+    struct SeedStructVariant<V> {
+        visitor: V,
+    }
+
+    impl<'de, V> de::DeserializeSeed<'de> for SeedStructVariant<V>
+    where
+        V: de::Visitor<'de>,
+    {
+        type Value = V::Value;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: de::Deserializer<'de>,
+        {
+            deserializer.deserialize_map(self.visitor)
+        }
+    }
+
     /// Avoid having to restate the generic types on `MapDeserializer`. The
     /// `Iterator::Item` contains enough information to figure out K and V.
     pub trait Pair {
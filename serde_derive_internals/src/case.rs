@@ -0,0 +1,236 @@
+// Copyright 2018 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Mapping between Rust identifier conventions and the case conventions that
+//! may be requested through `#[serde(rename_all = "...")]`.
+//!
+//! The transformation engine is exposed publicly so that crates generating
+//! companion code (schema generators, configuration-documentation tooling)
+//! can reproduce serde's exact serialized names without reimplementing the
+//! casing rules and risking drift.
+
+use std::str::FromStr;
+
+use self::RenameRule::*;
+
+/// The different possible ways to change the case of fields in a struct, or
+/// variants in an enum.
+#[derive(Copy, Clone, PartialEq)]
+pub enum RenameRule {
+    /// Rename direct children to "lowercase" style.
+    LowerCase,
+    /// Rename direct children to "UPPERCASE" style.
+    UpperCase,
+    /// Rename direct children to "PascalCase" style, as typically used for
+    /// enum variants.
+    PascalCase,
+    /// Rename direct children to "camelCase" style.
+    CamelCase,
+    /// Rename direct children to "snake_case" style, as commonly used for
+    /// fields.
+    SnakeCase,
+    /// Rename direct children to "SCREAMING_SNAKE_CASE" style, as commonly
+    /// used for constants.
+    ScreamingSnakeCase,
+    /// Rename direct children to "kebab-case" style.
+    KebabCase,
+    /// Rename direct children to "SCREAMING-KEBAB-CASE" style.
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    /// Apply a renaming rule to an enum variant, returning the version expected
+    /// in the source.
+    pub fn apply_to_variant(&self, variant: &str) -> String {
+        match *self {
+            PascalCase => variant.to_owned(),
+            LowerCase => variant.to_lowercase(),
+            UpperCase => variant.to_uppercase(),
+            CamelCase => variant[..1].to_lowercase() + &variant[1..],
+            SnakeCase => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            ScreamingSnakeCase => SnakeCase.apply_to_variant(variant).to_uppercase(),
+            KebabCase => SnakeCase.apply_to_variant(variant).replace('_', "-"),
+            ScreamingKebabCase => {
+                ScreamingSnakeCase.apply_to_variant(variant).replace('_', "-")
+            }
+        }
+    }
+
+    /// Apply a renaming rule to a struct field, returning the version expected
+    /// in the source.
+    pub fn apply_to_field(&self, field: &str) -> String {
+        match *self {
+            LowerCase | SnakeCase => field.to_owned(),
+            UpperCase => field.to_uppercase(),
+            PascalCase => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(ch.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            CamelCase => {
+                let pascal = PascalCase.apply_to_field(field);
+                pascal[..1].to_lowercase() + &pascal[1..]
+            }
+            ScreamingSnakeCase => field.to_uppercase(),
+            KebabCase => field.replace('_', "-"),
+            ScreamingKebabCase => ScreamingSnakeCase.apply_to_field(field).replace('_', "-"),
+        }
+    }
+}
+
+impl FromStr for RenameRule {
+    type Err = ();
+
+    fn from_str(rename_all_str: &str) -> Result<Self, Self::Err> {
+        match rename_all_str {
+            "lowercase" => Ok(LowerCase),
+            "UPPERCASE" => Ok(UpperCase),
+            "PascalCase" => Ok(PascalCase),
+            "camelCase" => Ok(CamelCase),
+            "snake_case" => Ok(SnakeCase),
+            "SCREAMING_SNAKE_CASE" => Ok(ScreamingSnakeCase),
+            "kebab-case" => Ok(KebabCase),
+            "SCREAMING-KEBAB-CASE" => Ok(ScreamingKebabCase),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The pair of renaming rules in effect on a container, one for serialization
+/// and one for deserialization, together with the precedence logic that lets
+/// an explicit per-field `#[serde(rename = "...")]` override the container
+/// rule.
+///
+/// Serialize and deserialize names can differ: a container may request a
+/// different `rename_all` in each direction, and a field may be renamed
+/// independently for each direction as well.
+#[derive(Copy, Clone)]
+pub struct RenameAllRules {
+    serialize: Option<RenameRule>,
+    deserialize: Option<RenameRule>,
+}
+
+impl RenameAllRules {
+    /// Construct the rules from the optional container `rename_all` settings
+    /// for each direction.
+    pub fn new(serialize: Option<RenameRule>, deserialize: Option<RenameRule>) -> Self {
+        RenameAllRules {
+            serialize: serialize,
+            deserialize: deserialize,
+        }
+    }
+
+    /// Resolve the serialized and deserialized names of a variant. An explicit
+    /// per-variant rename beats the container rule; otherwise the container
+    /// `rename_all` (if any) is applied, falling back to the original name.
+    pub fn apply_to_variant(
+        &self,
+        variant: &str,
+        rename: (Option<&str>, Option<&str>),
+    ) -> (String, String) {
+        let serialize = match rename.0 {
+            Some(name) => name.to_owned(),
+            None => match self.serialize {
+                Some(rule) => rule.apply_to_variant(variant),
+                None => variant.to_owned(),
+            },
+        };
+        let deserialize = match rename.1 {
+            Some(name) => name.to_owned(),
+            None => match self.deserialize {
+                Some(rule) => rule.apply_to_variant(variant),
+                None => variant.to_owned(),
+            },
+        };
+        (serialize, deserialize)
+    }
+
+    /// Resolve the serialized and deserialized names of a field, following the
+    /// same precedence rules as [`apply_to_variant`](RenameAllRules::apply_to_variant).
+    pub fn apply_to_field(
+        &self,
+        field: &str,
+        rename: (Option<&str>, Option<&str>),
+    ) -> (String, String) {
+        let serialize = match rename.0 {
+            Some(name) => name.to_owned(),
+            None => match self.serialize {
+                Some(rule) => rule.apply_to_field(field),
+                None => field.to_owned(),
+            },
+        };
+        let deserialize = match rename.1 {
+            Some(name) => name.to_owned(),
+            None => match self.deserialize {
+                Some(rule) => rule.apply_to_field(field),
+                None => field.to_owned(),
+            },
+        };
+        (serialize, deserialize)
+    }
+}
+
+#[test]
+fn rename_variants() {
+    for &(original, lower, upper, camel, snake, screaming, kebab, screaming_kebab) in &[
+        (
+            "Outcome", "outcome", "OUTCOME", "outcome", "outcome", "OUTCOME", "outcome",
+            "OUTCOME",
+        ),
+        (
+            "VeryTasty",
+            "verytasty",
+            "VERYTASTY",
+            "veryTasty",
+            "very_tasty",
+            "VERY_TASTY",
+            "very-tasty",
+            "VERY-TASTY",
+        ),
+    ] {
+        assert_eq!(LowerCase.apply_to_variant(original), lower);
+        assert_eq!(UpperCase.apply_to_variant(original), upper);
+        assert_eq!(PascalCase.apply_to_variant(original), original);
+        assert_eq!(CamelCase.apply_to_variant(original), camel);
+        assert_eq!(SnakeCase.apply_to_variant(original), snake);
+        assert_eq!(ScreamingSnakeCase.apply_to_variant(original), screaming);
+        assert_eq!(KebabCase.apply_to_variant(original), kebab);
+        assert_eq!(
+            ScreamingKebabCase.apply_to_variant(original),
+            screaming_kebab
+        );
+    }
+}
+
+#[test]
+fn explicit_rename_wins() {
+    let rules = RenameAllRules::new(Some(SnakeCase), Some(SnakeCase));
+    assert_eq!(
+        rules.apply_to_field("foo_bar", (Some("ser"), None)),
+        ("ser".to_owned(), "foo_bar".to_owned())
+    );
+}
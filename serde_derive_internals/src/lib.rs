@@ -0,0 +1,16 @@
+// Copyright 2018 Serde Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Internals shared between `serde_derive` and crates that generate companion
+//! code. The casing engine behind `#[serde(rename_all = "...")]` is re-exported
+//! here so downstream derives reproduce serde's exact serialized names instead
+//! of reimplementing the rules.
+
+mod case;
+
+pub use case::{RenameAllRules, RenameRule};